@@ -7,11 +7,20 @@
 // modified, or distributed except according to those terms.
 
 use crate::io::ReadMysqlExt;
-use std::{borrow::Cow, cmp::min, convert::TryFrom, io};
+use std::{
+    borrow::Cow,
+    cmp::min,
+    collections::HashMap,
+    convert::TryFrom,
+    fmt, io,
+    sync::{Arc, OnceLock, RwLock},
+};
+#[cfg(feature = "zstd")]
+use std::io::{Read, Write};
 
 use saturating::Saturating as S;
 
-use super::BinlogEventHeader;
+use super::{BinlogEventHeader, Event};
 use crate::{
     binlog::{
         consts::{
@@ -31,8 +40,9 @@ pub struct TransactionPayloadEvent<'a> {
     // payload size
     payload_size: RawInt<LeU64>,
 
-    // compression algorithm
-    algorithm: TransactionPayloadCompressionType,
+    // raw wire code of the compression algorithm, kept even if unrecognized so that an unknown
+    // code is reported as an error at decompress time instead of panicking during parsing
+    algorithm: RawInt<LeU64>,
 
     // uncompressed size
     uncompressed_size: RawInt<LeU64>,
@@ -53,13 +63,49 @@ impl<'a> TransactionPayloadEvent<'a> {
     ) -> Self {
         Self {
             payload_size: RawInt::new(payload_size),
-            algorithm: algorithm,
+            algorithm: RawInt::new(algorithm as u64),
             uncompressed_size: RawInt::new(uncompressed_size),
             payload: RawBytes::new(payload),
             header_size: 0,
         }
     }
 
+    /// Builds a `TransactionPayloadEvent` by serializing `events` into one contiguous buffer and,
+    /// unless `algorithm` is [`TransactionPayloadCompressionType::NONE`], compressing it with
+    /// `zstd` at `level`. `window_log` is forwarded to the encoder when given, trading memory for
+    /// ratio on the compressor side (see [`zstd::stream::write::Encoder::window_log`]).
+    ///
+    /// This is the write-side counterpart to [`Self::events`]: the serialized length is recorded
+    /// as `uncompressed_size` so that a later [`Self::decompress_payload`] knows how much to
+    /// allocate.
+    pub fn compress(
+        events: &[Event<'_>],
+        algorithm: TransactionPayloadCompressionType,
+        level: i32,
+        window_log: Option<u32>,
+    ) -> io::Result<TransactionPayloadEvent<'static>> {
+        let mut raw = Vec::new();
+        for event in events {
+            event.serialize(&mut raw);
+        }
+        let uncompressed_size = raw.len() as u64;
+
+        let code = algorithm as u64;
+        let codec = codec_for_code(code)
+            .ok_or(TransactionPayloadError::UnknownCompressionType(code))?;
+        let payload = codec
+            .compress(&raw, level, window_log)
+            .map_err(TransactionPayloadError::Codec)?;
+
+        Ok(TransactionPayloadEvent {
+            payload_size: RawInt::new(payload.len() as u64),
+            algorithm: RawInt::new(code),
+            uncompressed_size: RawInt::new(uncompressed_size),
+            payload: RawBytes::new(payload),
+            header_size: 0,
+        })
+    }
+
     /// Sets the `payload_size` field value.
     pub fn with_payload_size(mut self, payload_size: u64) -> Self {
         self.payload_size = RawInt::new(payload_size);
@@ -67,7 +113,7 @@ impl<'a> TransactionPayloadEvent<'a> {
     }
     /// Sets the `algorithm` field value.
     pub fn with_algorithm(mut self, algorithm: TransactionPayloadCompressionType) -> Self {
-        self.algorithm = algorithm;
+        self.algorithm = RawInt::new(algorithm as u64);
         self
     }
     /// Sets the `uncompressed_size` field value.
@@ -92,24 +138,119 @@ impl<'a> TransactionPayloadEvent<'a> {
         self.payload.as_bytes()
     }
 
+    /// Returns an iterator that decompresses and parses the inner binlog events one at a time.
+    ///
+    /// Bytes are pulled from [`self.algorithm`](Self::algorithm_code)'s
+    /// [`TransactionPayloadCodec::reader`] on demand, just enough at a time to parse a single
+    /// [`BinlogEventHeader`] and its body, in the same spirit as
+    /// [`crate::binlog::EventStreamReader`] walking a binlog file event by event. Whether this
+    /// actually avoids materializing the whole decompressed transaction in memory depends on the
+    /// codec: `zstd` streams incrementally, while `NONE` and any codec that doesn't override
+    /// `reader` decode eagerly — either way, inner events are still parsed and size-limited one
+    /// at a time.
+    ///
+    /// Inner events of a transaction payload are always written by a server new enough to
+    /// support binlog transaction compression, so they are parsed as [`BinlogVersion::Version4`].
+    ///
+    /// Each inner event's size comes from its own (attacker-controllable) header, not from
+    /// `uncompressed_size`, so this is equivalent to [`Self::events_limited`] with a limit of
+    /// `u32::MAX` — the most a single event's 4-byte size field can declare. Prefer
+    /// [`Self::events_limited`] with a tighter limit when consuming binlogs from the network.
+    pub fn events(&self) -> io::Result<TransactionPayloadEventStream<'_>> {
+        self.events_limited(u32::MAX as u64)
+    }
+
+    /// Like [`Self::events`], but rejects any inner event whose declared body size exceeds
+    /// `max_event_size` instead of eagerly allocating a buffer for it.
+    pub fn events_limited(&self, max_event_size: u64) -> io::Result<TransactionPayloadEventStream<'_>> {
+        TransactionPayloadEventStream::new(self.payload.as_bytes(), self.algorithm.0, max_event_size)
+    }
+
     /// Returns raw payload decompressed (see [`crate::binlog::EventStreamReader::read_decompressed`]).
+    ///
+    /// Falls back to an empty `Vec` on any failure; use [`Self::try_decompress_payload`] to
+    /// distinguish an unrecognized algorithm, a corrupt payload, or a declared-size mismatch.
     pub fn decompress_payload(self) -> Vec<u8> {
-        if self.algorithm == TransactionPayloadCompressionType::NONE {
-            return self.payload_raw().to_vec();
-        }
-        let mut decode_buf = vec![0_u8; self.uncompressed_size.0 as usize];
-        match zstd::stream::copy_decode(self.payload.as_bytes(), &mut decode_buf[..]) {
-            Ok(_) => {}
-            Err(_) => {
-                return Vec::new();
-            }
-        };
-        decode_buf
+        self.try_decompress_payload().unwrap_or_default()
+    }
+
+    /// Decompresses the payload, returning a [`TransactionPayloadError`] instead of silently
+    /// producing an empty or truncated result.
+    ///
+    /// This makes it safe to drive directly off an untrusted replication stream: an unrecognized
+    /// compression code, a corrupt payload, or (for a compressed payload) an `uncompressed_size`
+    /// that doesn't match what was actually decoded are all reported rather than treated as
+    /// empty. A `NONE` payload is returned as is, without comparing it to `uncompressed_size`.
+    pub fn try_decompress_payload(&self) -> Result<Vec<u8>, TransactionPayloadError> {
+        let codec = codec_for_code(self.algorithm.0)
+            .ok_or(TransactionPayloadError::UnknownCompressionType(self.algorithm.0))?;
+        let decoded = codec
+            .decompress(self.payload.as_bytes(), self.uncompressed_size.0 as usize)
+            .map_err(TransactionPayloadError::Codec)?;
+
+        if self.algorithm.0 != TransactionPayloadCompressionType::NONE as u64
+            && decoded.len() as u64 != self.uncompressed_size.0
+        {
+            return Err(TransactionPayloadError::UncompressedSizeMismatch {
+                expected: self.uncompressed_size.0,
+                actual: decoded.len(),
+            });
+        }
+
+        Ok(decoded)
+    }
+
+    /// Like [`Self::decompress_payload`], but rejects payloads whose declared or actual
+    /// uncompressed size exceeds `limit` instead of eagerly allocating up to that size.
+    ///
+    /// The attacker-controlled `uncompressed_size` field is checked up front, and decoding is
+    /// then bounded so a payload that lies about its size is still caught rather than being
+    /// allowed to decode past the limit. Use this instead of [`Self::decompress_payload`] when
+    /// consuming binlogs from the network.
+    pub fn decompress_payload_limited(self, limit: u64) -> Result<Vec<u8>, TransactionPayloadError> {
+        self.try_decompress_payload_limited(limit)
+    }
+
+    /// Fallible, bounded counterpart of [`Self::try_decompress_payload`]; see
+    /// [`Self::decompress_payload_limited`] for the limit semantics.
+    pub fn try_decompress_payload_limited(
+        &self,
+        limit: u64,
+    ) -> Result<Vec<u8>, TransactionPayloadError> {
+        if self.uncompressed_size.0 > limit {
+            return Err(TransactionPayloadError::UncompressedSizeExceedsLimit {
+                limit,
+                declared: self.uncompressed_size.0,
+            });
+        }
+
+        let codec = codec_for_code(self.algorithm.0)
+            .ok_or(TransactionPayloadError::UnknownCompressionType(self.algorithm.0))?;
+        let decoded = codec
+            .decompress_limited(self.payload.as_bytes(), self.uncompressed_size.0 as usize, limit)
+            .map_err(TransactionPayloadError::Codec)?;
+
+        if self.algorithm.0 != TransactionPayloadCompressionType::NONE as u64
+            && decoded.len() as u64 != self.uncompressed_size.0
+        {
+            return Err(TransactionPayloadError::UncompressedSizeMismatch {
+                expected: self.uncompressed_size.0,
+                actual: decoded.len(),
+            });
+        }
+
+        Ok(decoded)
     }
 
-    /// Returns the algorithm.
-    pub fn algorithm(&self) -> TransactionPayloadCompressionType {
-        self.algorithm
+    /// Returns the algorithm, or `None` if the wire code is not a recognized
+    /// [`TransactionPayloadCompressionType`].
+    pub fn algorithm(&self) -> Option<TransactionPayloadCompressionType> {
+        TransactionPayloadCompressionType::try_from(self.algorithm.0).ok()
+    }
+
+    /// Returns the raw wire code of the compression algorithm, even if unrecognized.
+    pub fn algorithm_code(&self) -> u64 {
+        self.algorithm.0
     }
 
     /// Returns the uncompressed_size.
@@ -134,7 +275,7 @@ impl<'de> MyDeserialize<'de> for TransactionPayloadEvent<'de> {
     fn deserialize(_ctx: Self::Ctx, buf: &mut ParseBuf<'de>) -> io::Result<Self> {
         let mut ob = Self {
             payload_size: RawInt::new(0),
-            algorithm: TransactionPayloadCompressionType::NONE,
+            algorithm: RawInt::new(TransactionPayloadCompressionType::NONE as u64),
             uncompressed_size: RawInt::new(0),
             payload: RawBytes::from("".as_bytes()),
             header_size: 0,
@@ -149,20 +290,17 @@ impl<'de> MyDeserialize<'de> for TransactionPayloadEvent<'de> {
                 // we have reached the end of the header
                 Ok(TransactionPayloadFields::OTW_PAYLOAD_HEADER_END_MARK) => {
                     if !have_payload_size || !have_compression_type {
-                        Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            format!("Missing field in payload header"),
-                        ))?;
+                        Err(TransactionPayloadError::MissingField(if !have_payload_size {
+                            "payload_size"
+                        } else {
+                            "compression_type"
+                        }))?;
                     }
                     if ob.payload_size.0 as usize > buf.len() {
-                        Err(io::Error::new(
-                            io::ErrorKind::InvalidData,
-                            format!(
-                                "Payload size is bigger than the remaining buffer: {} > {}",
-                                ob.payload_size.0,
-                                buf.len()
-                            ),
-                        ))?;
+                        Err(TransactionPayloadError::PayloadSizeOutOfBounds {
+                            declared: ob.payload_size.0,
+                            remaining: buf.len(),
+                        })?;
                     }
                     ob.header_size = original_buf_size - ob.payload_size.0 as usize;
                     let mut payload_buf: ParseBuf = buf.parse(ob.payload_size.0 as usize)?;
@@ -180,7 +318,10 @@ impl<'de> MyDeserialize<'de> for TransactionPayloadEvent<'de> {
                 Ok(TransactionPayloadFields::OTW_PAYLOAD_COMPRESSION_TYPE_FIELD) => {
                     let _length = buf.read_lenenc_int()?;
                     let val = buf.read_lenenc_int()?;
-                    ob.algorithm = TransactionPayloadCompressionType::try_from(val).unwrap();
+                    // Keep the raw code even if it doesn't match a known
+                    // `TransactionPayloadCompressionType` so that an unrecognized algorithm is
+                    // reported as an error at decompress time instead of panicking here.
+                    ob.algorithm = RawInt::new(val);
                     have_compression_type = true;
                     continue;
                 }
@@ -205,10 +346,10 @@ impl<'de> MyDeserialize<'de> for TransactionPayloadEvent<'de> {
 impl MySerialize for TransactionPayloadEvent<'_> {
     fn serialize(&self, buf: &mut Vec<u8>) {
         buf.put_lenenc_int(TransactionPayloadFields::OTW_PAYLOAD_COMPRESSION_TYPE_FIELD as u64);
-        buf.put_lenenc_int(crate::misc::lenenc_int_len(self.algorithm as u64) as u64);
-        buf.put_lenenc_int(self.algorithm as u64);
+        buf.put_lenenc_int(crate::misc::lenenc_int_len(self.algorithm.0) as u64);
+        buf.put_lenenc_int(self.algorithm.0);
 
-        if self.algorithm != TransactionPayloadCompressionType::NONE {
+        if self.algorithm.0 != TransactionPayloadCompressionType::NONE as u64 {
             buf.put_lenenc_int(
                 TransactionPayloadFields::OTW_PAYLOAD_UNCOMPRESSED_SIZE_FIELD as u64,
             );
@@ -226,6 +367,286 @@ impl MySerialize for TransactionPayloadEvent<'_> {
     }
 }
 
+/// An error encountered while parsing or decompressing a [`TransactionPayloadEvent`].
+#[derive(Debug)]
+pub enum TransactionPayloadError {
+    /// The payload header was missing a required field.
+    MissingField(&'static str),
+    /// `payload_size` claims more bytes than remain in the buffer.
+    PayloadSizeOutOfBounds { declared: u64, remaining: usize },
+    /// No codec is registered for this wire compression-type code.
+    UnknownCompressionType(u64),
+    /// The decompressed payload length did not match the declared `uncompressed_size`.
+    UncompressedSizeMismatch { expected: u64, actual: usize },
+    /// The declared `uncompressed_size` exceeds a caller-supplied limit (see
+    /// [`TransactionPayloadEvent::try_decompress_payload_limited`]). An actual decoded length
+    /// over the limit is reported by the codec, as [`Self::Codec`].
+    UncompressedSizeExceedsLimit { limit: u64, declared: u64 },
+    /// The underlying codec failed to compress or decompress the payload.
+    Codec(io::Error),
+}
+
+impl fmt::Display for TransactionPayloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingField(name) => write!(f, "missing `{name}` field in payload header"),
+            Self::PayloadSizeOutOfBounds { declared, remaining } => write!(
+                f,
+                "payload size is bigger than the remaining buffer: {declared} > {remaining}"
+            ),
+            Self::UnknownCompressionType(code) => {
+                write!(f, "no codec registered for compression type {code}")
+            }
+            Self::UncompressedSizeMismatch { expected, actual } => write!(
+                f,
+                "decompressed payload length {actual} does not match declared uncompressed_size {expected}"
+            ),
+            Self::UncompressedSizeExceedsLimit { limit, declared } => write!(
+                f,
+                "uncompressed size {declared} exceeds the limit of {limit} bytes"
+            ),
+            Self::Codec(e) => write!(f, "codec error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for TransactionPayloadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Codec(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<TransactionPayloadError> for io::Error {
+    fn from(err: TransactionPayloadError) -> Self {
+        match err {
+            TransactionPayloadError::Codec(e) => e,
+            other => io::Error::new(io::ErrorKind::InvalidData, other),
+        }
+    }
+}
+
+/// Compresses and decompresses [`TransactionPayloadEvent`] payloads for one wire compression
+/// code.
+///
+/// Built-in codecs are looked up by raw [`TransactionPayloadCompressionType`] code via
+/// [`codec_for_code`]. The `zstd` codec lives behind the crate's default-on `zstd` feature so
+/// that targets that can't or don't want to link libzstd can drop it.
+pub trait TransactionPayloadCodec {
+    /// Decompresses `input`. `hint_len` is the payload's declared `uncompressed_size` and may be
+    /// used to preallocate the output buffer, but must not be trusted blindly since it comes from
+    /// the wire.
+    fn decompress(&self, input: &[u8], hint_len: usize) -> io::Result<Vec<u8>>;
+
+    /// Like [`Self::decompress`], but must stop and return an error once more than `limit` bytes
+    /// have been produced, even if `hint_len` understates the true decompressed size. Protects
+    /// against a declared size that lies, not just one that's merely large.
+    fn decompress_limited(&self, input: &[u8], hint_len: usize, limit: u64) -> io::Result<Vec<u8>>;
+
+    /// Compresses `input` at `level`, optionally tuning the encoder's window log.
+    fn compress(&self, input: &[u8], level: i32, window_log: Option<u32>) -> io::Result<Vec<u8>>;
+
+    /// Returns a reader that produces `input` decompressed on demand, for callers (such as
+    /// [`TransactionPayloadEventStream`]) that want to parse inner events one at a time instead
+    /// of materializing the whole decompressed payload upfront.
+    ///
+    /// The default implementation just calls [`Self::decompress`] eagerly and wraps the result in
+    /// a [`std::io::Cursor`], so every codec gets a working reader for free; override this when
+    /// the underlying format actually supports incremental decoding (see `ZstdCodec`) to avoid
+    /// paying for the eager allocation.
+    fn reader<'r>(&self, input: &'r [u8]) -> io::Result<Box<dyn io::Read + 'r>> {
+        Ok(Box::new(io::Cursor::new(self.decompress(input, input.len())?)))
+    }
+}
+
+struct NoneCodec;
+
+impl TransactionPayloadCodec for NoneCodec {
+    fn decompress(&self, input: &[u8], _hint_len: usize) -> io::Result<Vec<u8>> {
+        Ok(input.to_vec())
+    }
+
+    fn decompress_limited(&self, input: &[u8], _hint_len: usize, limit: u64) -> io::Result<Vec<u8>> {
+        if input.len() as u64 > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("payload of {} bytes exceeds the limit of {limit} bytes", input.len()),
+            ));
+        }
+        Ok(input.to_vec())
+    }
+
+    fn compress(&self, input: &[u8], _level: i32, _window_log: Option<u32>) -> io::Result<Vec<u8>> {
+        Ok(input.to_vec())
+    }
+}
+
+// Requires the crate's Cargo.toml to declare `zstd` as an optional dependency that's part of
+// `default`, e.g. `zstd = { version = "...", optional = true }` plus
+// `zstd = ["dep:zstd"]` / `default = [..., "zstd"]` under `[features]`.
+#[cfg(feature = "zstd")]
+struct ZstdCodec;
+
+#[cfg(feature = "zstd")]
+impl TransactionPayloadCodec for ZstdCodec {
+    fn decompress(&self, input: &[u8], hint_len: usize) -> io::Result<Vec<u8>> {
+        // Decode into a buffer we grow as bytes actually arrive, rather than a fixed
+        // `vec![0; hint_len]`: the latter's length always equals `hint_len` by construction, so
+        // a payload that understates how much data it really holds would silently come back
+        // zero-padded instead of tripping the caller's `uncompressed_size` mismatch check.
+        let mut decoder = zstd::stream::read::Decoder::new(input)?;
+        let mut out = Vec::with_capacity(hint_len);
+        io::copy(&mut decoder, &mut out)?;
+        Ok(out)
+    }
+
+    fn decompress_limited(&self, input: &[u8], hint_len: usize, limit: u64) -> io::Result<Vec<u8>> {
+        let decoder = zstd::stream::read::Decoder::new(input)?;
+        let mut out = Vec::with_capacity(hint_len.min(limit as usize));
+        // Read one byte past the limit so we can tell "exactly at the limit" apart from "over
+        // it" without trusting the attacker-controlled `hint_len`/declared uncompressed size.
+        io::copy(&mut decoder.take(limit.saturating_add(1)), &mut out)?;
+        if out.len() as u64 > limit {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("decompressed payload exceeds the limit of {limit} bytes"),
+            ));
+        }
+        Ok(out)
+    }
+
+    fn compress(&self, input: &[u8], level: i32, window_log: Option<u32>) -> io::Result<Vec<u8>> {
+        let mut encoder = zstd::stream::write::Encoder::new(Vec::new(), level)?;
+        if let Some(window_log) = window_log {
+            encoder.window_log(window_log)?;
+        }
+        encoder.write_all(input)?;
+        encoder.finish()
+    }
+
+    fn reader<'r>(&self, input: &'r [u8]) -> io::Result<Box<dyn io::Read + 'r>> {
+        Ok(Box::new(zstd::stream::read::Decoder::new(input)?))
+    }
+}
+
+type CodecRegistry = RwLock<HashMap<u64, Arc<dyn TransactionPayloadCodec + Send + Sync>>>;
+
+fn custom_codecs() -> &'static CodecRegistry {
+    static REGISTRY: OnceLock<CodecRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// Registers a [`TransactionPayloadCodec`] for a wire compression-type `code` not already
+/// handled by a built-in codec (`NONE`, and `ZSTD` when the `zstd` feature is enabled).
+///
+/// This is how a caller plugs in support for an algorithm this crate doesn't recognize, for
+/// every [`TransactionPayloadEvent`] in the process: registering a codec for a code that was
+/// already passed to `register_codec` replaces the earlier one. Built-in codecs always take
+/// priority and cannot be overridden this way.
+pub fn register_codec(code: u64, codec: impl TransactionPayloadCodec + Send + Sync + 'static) {
+    custom_codecs()
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(code, Arc::new(codec));
+}
+
+/// Looks up the [`TransactionPayloadCodec`] for a raw compression-type wire code: the built-in
+/// codec if one matches, otherwise whatever was last passed to [`register_codec`] for that code.
+///
+/// Returns `None` for a code with no registered codec, e.g. an algorithm the peer understands
+/// but this build does not (such as `ZSTD` when the `zstd` feature is disabled) or one nothing
+/// has registered support for.
+fn codec_for_code(code: u64) -> Option<Arc<dyn TransactionPayloadCodec + Send + Sync>> {
+    if code == TransactionPayloadCompressionType::NONE as u64 {
+        return Some(Arc::new(NoneCodec));
+    }
+    #[cfg(feature = "zstd")]
+    if code == TransactionPayloadCompressionType::ZSTD as u64 {
+        return Some(Arc::new(ZstdCodec));
+    }
+    custom_codecs()
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .get(&code)
+        .cloned()
+}
+
+/// Lazily decompresses and parses the inner binlog events of a [`TransactionPayloadEvent`].
+///
+/// Created via [`TransactionPayloadEvent::events`] or [`TransactionPayloadEvent::events_limited`].
+pub struct TransactionPayloadEventStream<'a> {
+    reader: Box<dyn io::Read + 'a>,
+    max_event_size: u64,
+    exhausted: bool,
+}
+
+impl<'a> TransactionPayloadEventStream<'a> {
+    fn new(payload: &'a [u8], algorithm_code: u64, max_event_size: u64) -> io::Result<Self> {
+        let codec = codec_for_code(algorithm_code)
+            .ok_or(TransactionPayloadError::UnknownCompressionType(algorithm_code))?;
+        Ok(Self {
+            reader: codec.reader(payload)?,
+            max_event_size,
+            exhausted: false,
+        })
+    }
+
+    fn read_event(&mut self) -> io::Result<Option<Event<'static>>> {
+        let mut header_buf = [0_u8; BinlogEventHeader::LEN];
+        match self.reader.read_exact(&mut header_buf) {
+            Ok(()) => (),
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        }
+
+        let header = BinlogEventHeader::deserialize((), &mut ParseBuf(&header_buf))?;
+
+        // `event_size` comes from the inner (decompressed) event's own header, which is just as
+        // attacker-controlled as the outer payload's `uncompressed_size` — reject it up front
+        // rather than letting a single corrupt inner event force a multi-GB allocation.
+        let body_len = (header.event_size() as u64).saturating_sub(BinlogEventHeader::LEN as u64);
+        if body_len > self.max_event_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "inner event body of {body_len} bytes exceeds the limit of {} bytes",
+                    self.max_event_size
+                ),
+            ));
+        }
+        let mut body_buf = vec![0_u8; body_len as usize];
+        self.reader.read_exact(&mut body_buf)?;
+
+        let ctx = BinlogCtx::new(BinlogVersion::Version4, &header);
+        let event = Event::deserialize(ctx, &mut ParseBuf(&body_buf))?;
+        Ok(Some(event.into_owned()))
+    }
+}
+
+impl<'a> Iterator for TransactionPayloadEventStream<'a> {
+    type Item = io::Result<Event<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        match self.read_event() {
+            Ok(Some(event)) => Some(Ok(event)),
+            Ok(None) => {
+                self.exhausted = true;
+                None
+            }
+            Err(e) => {
+                self.exhausted = true;
+                Some(Err(e))
+            }
+        }
+    }
+}
+
 impl<'a> BinlogEvent<'a> for TransactionPayloadEvent<'a> {
     const EVENT_TYPE: EventType = EventType::TRANSACTION_PAYLOAD_EVENT;
 }
@@ -238,4 +659,214 @@ impl<'a> BinlogStruct<'a> for TransactionPayloadEvent<'a> {
 
         min(len.0, u32::MAX as usize - BinlogEventHeader::LEN)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct UppercaseCodec;
+
+    impl TransactionPayloadCodec for UppercaseCodec {
+        fn decompress(&self, input: &[u8], _hint_len: usize) -> io::Result<Vec<u8>> {
+            Ok(input.to_ascii_uppercase())
+        }
+
+        fn decompress_limited(
+            &self,
+            input: &[u8],
+            hint_len: usize,
+            _limit: u64,
+        ) -> io::Result<Vec<u8>> {
+            self.decompress(input, hint_len)
+        }
+
+        fn compress(
+            &self,
+            input: &[u8],
+            _level: i32,
+            _window_log: Option<u32>,
+        ) -> io::Result<Vec<u8>> {
+            Ok(input.to_ascii_uppercase())
+        }
+    }
+
+    // Each test below that needs a non-built-in wire code picks its own and never reuses another
+    // test's: `codec_for_code`
+    // is backed by a process-wide registry, so sharing a code between tests that run
+    // concurrently would make one test observe another's `register_codec` call.
+
+    #[test]
+    fn unknown_compression_type_errors_instead_of_panicking() {
+        // `new`/`with_algorithm` can't construct an unrecognized `TransactionPayloadCompressionType`
+        // directly, so poke the raw field to simulate what deserializing an unrecognized wire
+        // code produces.
+        let mut event =
+            TransactionPayloadEvent::new(0, TransactionPayloadCompressionType::NONE, 0, Vec::new());
+        event.algorithm = RawInt::new(54321);
+
+        match event.try_decompress_payload() {
+            Err(TransactionPayloadError::UnknownCompressionType(code)) => assert_eq!(code, 54321),
+            other => panic!("expected UnknownCompressionType, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn register_codec_plugs_in_support_for_an_unrecognized_code() {
+        const CUSTOM_CODE: u64 = 98765;
+        assert!(codec_for_code(CUSTOM_CODE).is_none());
+
+        register_codec(CUSTOM_CODE, UppercaseCodec);
+
+        let codec = codec_for_code(CUSTOM_CODE).expect("codec should now be registered");
+        assert_eq!(codec.decompress(b"hi", 2).unwrap(), b"HI");
+    }
+
+    #[test]
+    fn compress_with_none_stores_the_serialized_events_untouched() {
+        let event =
+            TransactionPayloadEvent::compress(&[], TransactionPayloadCompressionType::NONE, 0, None)
+                .unwrap();
+
+        assert_eq!(event.algorithm(), Some(TransactionPayloadCompressionType::NONE));
+        assert_eq!(event.payload_size(), 0);
+        assert_eq!(event.uncompressed_size(), 0);
+        assert_eq!(event.decompress_payload(), Vec::<u8>::new());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn compress_then_try_decompress_payload_round_trips() {
+        let event =
+            TransactionPayloadEvent::compress(&[], TransactionPayloadCompressionType::ZSTD, 3, None)
+                .unwrap();
+
+        assert_eq!(event.algorithm(), Some(TransactionPayloadCompressionType::ZSTD));
+        assert_eq!(event.uncompressed_size(), 0);
+        assert_eq!(event.try_decompress_payload().unwrap(), Vec::<u8>::new());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn events_on_a_compressed_empty_payload_yields_no_events() {
+        let event =
+            TransactionPayloadEvent::compress(&[], TransactionPayloadCompressionType::ZSTD, 3, None)
+                .unwrap();
+
+        let events: io::Result<Vec<_>> = event.events().unwrap().collect();
+        assert!(events.unwrap().is_empty());
+    }
+
+    #[test]
+    fn none_codec_reader_returns_raw_bytes_without_zstd_framing() {
+        use std::io::Read as _;
+
+        // Deliberately not a valid zstd frame (wrong magic bytes): `events()`/`events_limited()`
+        // on a `NONE`-algorithm payload must read this untouched, never through a zstd decoder.
+        let raw = b"not a zstd frame, just the raw concatenated inner events";
+
+        let codec = codec_for_code(TransactionPayloadCompressionType::NONE as u64).unwrap();
+        let mut reader = codec.reader(raw).unwrap();
+        let mut out = Vec::new();
+        reader.read_to_end(&mut out).unwrap();
+
+        assert_eq!(out, raw);
+    }
+
+    #[test]
+    fn events_on_a_none_algorithm_empty_payload_yields_no_events() {
+        // `NONE` payloads are stored raw, not zstd-framed; `events()` used to always build a
+        // zstd decoder regardless of `algorithm`, which fails on the very first read for any
+        // non-empty `NONE` payload (see `none_codec_reader_returns_raw_bytes_without_zstd_framing`
+        // for that case). This exercises the same `algorithm == NONE` path end to end.
+        let event =
+            TransactionPayloadEvent::compress(&[], TransactionPayloadCompressionType::NONE, 0, None)
+                .unwrap();
+
+        let events: io::Result<Vec<_>> = event.events().unwrap().collect();
+        assert!(events.unwrap().is_empty());
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn try_decompress_payload_limited_rejects_oversized_declared_size() {
+        let plaintext = b"a binlog event body";
+        let mut compressed = Vec::new();
+        zstd::stream::copy_encode(&plaintext[..], &mut compressed, 3).unwrap();
+
+        let event = TransactionPayloadEvent::new(
+            compressed.len() as u64,
+            TransactionPayloadCompressionType::ZSTD,
+            plaintext.len() as u64,
+            compressed,
+        );
+
+        match event.try_decompress_payload_limited(plaintext.len() as u64 - 1) {
+            Err(TransactionPayloadError::UncompressedSizeExceedsLimit { limit, declared }) => {
+                assert_eq!(limit, plaintext.len() as u64 - 1);
+                assert_eq!(declared, plaintext.len() as u64);
+            }
+            other => panic!("expected UncompressedSizeExceedsLimit, got {other:?}"),
+        }
+
+        assert_eq!(
+            event
+                .try_decompress_payload_limited(plaintext.len() as u64)
+                .unwrap(),
+            plaintext
+        );
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn events_limited_rejects_inner_event_that_lies_about_its_size() {
+        // Binlog v4 event header: timestamp(4) + type_code(1) + server_id(4) + event_size(4) +
+        // log_pos(4) + flags(2). `event_size` is the 4-byte field starting right after
+        // timestamp + type_code + server_id, i.e. at offset 9, and counts the whole event
+        // (header included).
+        let mut header = vec![0_u8; BinlogEventHeader::LEN];
+        let declared_event_size = 1_u32 << 30; // 1 GiB, nowhere near actually present
+        header[9..13].copy_from_slice(&declared_event_size.to_le_bytes());
+
+        let mut compressed = Vec::new();
+        zstd::stream::copy_encode(&header[..], &mut compressed, 3).unwrap();
+
+        let event = TransactionPayloadEvent::new(
+            compressed.len() as u64,
+            TransactionPayloadCompressionType::ZSTD,
+            header.len() as u64,
+            compressed,
+        );
+
+        let mut stream = event.events_limited(1024).unwrap();
+        let err = stream
+            .next()
+            .expect("stream should yield the rejected event")
+            .expect_err("oversized inner event should error, not allocate 1 GiB");
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn try_decompress_payload_reports_uncompressed_size_mismatch() {
+        let plaintext = b"a binlog event body";
+        let mut compressed = Vec::new();
+        zstd::stream::copy_encode(&plaintext[..], &mut compressed, 3).unwrap();
+
+        // Declare a size far bigger than what's actually in the stream.
+        let event = TransactionPayloadEvent::new(
+            compressed.len() as u64,
+            TransactionPayloadCompressionType::ZSTD,
+            plaintext.len() as u64 + 1000,
+            compressed,
+        );
+
+        match event.try_decompress_payload() {
+            Err(TransactionPayloadError::UncompressedSizeMismatch { expected, actual }) => {
+                assert_eq!(expected, plaintext.len() as u64 + 1000);
+                assert_eq!(actual, plaintext.len());
+            }
+            other => panic!("expected UncompressedSizeMismatch, got {other:?}"),
+        }
+    }
 }
\ No newline at end of file